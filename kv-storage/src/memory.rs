@@ -3,17 +3,23 @@ use std::collections::HashMap;
 
 type Snapshot = HashMap<Hash256, Vec<u8>>;
 
+/// An in-memory `KVStorage` backed by a stack of snapshots.
+///
+/// Each `commit_checkpoint` pushes a copy of the current revision onto
+/// `checkpoints`, and `revert_to_latest_checkpoint` pops the top of that
+/// stack back into `current_revision`, so checkpoints may be nested to
+/// arbitrary depth and unwound one at a time.
 #[derive(Clone)]
 pub struct MemoryDB {
     current_revision: Snapshot,
-    checkpoint: Snapshot,
+    checkpoints: Vec<Snapshot>,
 }
 
 impl MemoryDB {
     pub async fn new() -> Self {
         MemoryDB {
             current_revision: Snapshot::new(),
-            checkpoint: Snapshot::new(),
+            checkpoints: Vec::new(),
         }
     }
 
@@ -25,12 +31,15 @@ impl MemoryDB {
 #[async_trait]
 impl KVStorage for MemoryDB {
     async fn commit_checkpoint(&mut self) -> Result<(), Error> {
-        self.checkpoint = self.current_revision.clone();
+        self.checkpoints.push(self.current_revision.clone());
         Ok(())
     }
 
     async fn revert_to_latest_checkpoint(&mut self) -> Result<(), Error> {
-        self.current_revision = self.checkpoint.clone();
+        self.current_revision = self
+            .checkpoints
+            .pop()
+            .ok_or_else(|| Error::Unknown("no checkpoint to revert to".to_owned()))?;
         Ok(())
     }
 
@@ -75,7 +84,7 @@ mod test {
     }
 
     async fn get_from_checkpoint(db: &MemoryDB, key: &str, value: &[u8]) -> bool {
-        match db.checkpoint.get(&Hash256::hash(key)) {
+        match db.checkpoints.last().and_then(|c| c.get(&Hash256::hash(key))) {
             Some(v) => v == value,
             None => false,
         }
@@ -114,7 +123,13 @@ mod test {
     #[tokio::test]
     async fn get_from_empty_checkpoint() {
         let db: MemoryDB = MemoryDB::new().await;
-        assert_eq!(db.checkpoint.get(&Hash256::hash("1")), None);
+        assert!(db.checkpoints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn revert_without_checkpoint_fails() {
+        let mut db: MemoryDB = init().await;
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
     }
 
     #[tokio::test]
@@ -147,4 +162,22 @@ mod test {
         revert_checkpoint_handler(&mut db).await;
         assert!(!get_from_db(&db, "5", b"5").await);
     }
+
+    #[tokio::test]
+    async fn nested_checkpoints_unwind_one_at_a_time() {
+        let mut db: MemoryDB = init().await;
+        commit_checkpoint_handler(&mut db).await; // checkpoint A: {1, 2, 3, 4}
+        insert_or_update_handler(&mut db, "5", b"5").await;
+        commit_checkpoint_handler(&mut db).await; // checkpoint B: {1, 2, 3, 4, 5}
+        insert_or_update_handler(&mut db, "6", b"6").await;
+        assert!(get_from_db(&db, "6", b"6").await);
+
+        revert_checkpoint_handler(&mut db).await; // back to checkpoint B
+        assert!(!get_from_db(&db, "6", b"6").await);
+        assert!(get_from_db(&db, "5", b"5").await);
+
+        revert_checkpoint_handler(&mut db).await; // back to checkpoint A
+        assert!(!get_from_db(&db, "5", b"5").await);
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
+    }
 }
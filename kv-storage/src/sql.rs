@@ -0,0 +1,262 @@
+use super::*;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// A `KVStorage` backed by an embedded SQLite database, so state survives process
+/// restarts.
+///
+/// `kv` always holds the current revision and is written through ordinary
+/// autocommitted transactions, so every `insert_or_update`/`remove` is durable as
+/// soon as it returns. Checkpoints do not hold any transaction open across calls
+/// (which would make everything written after the first checkpoint vanish on a
+/// plain process exit); instead each `commit_checkpoint` copies `kv` into
+/// `checkpoint_data` under a new `layer_id`, and `revert_to_latest_checkpoint`
+/// copies the most recent layer back and drops it, both in a single committed
+/// transaction. The layer tables are themselves persisted, so the checkpoint
+/// stack survives a restart exactly like the data it guards.
+pub struct SqliteDB {
+    connection: Connection,
+}
+
+impl SqliteDB {
+    /// Creates a fresh database at `path`, failing if one already exists.
+    pub async fn new(path: &str) -> Result<Self, Error> {
+        if std::path::Path::new(path).exists() {
+            return Err(Error::Unknown(format!("{path} already exists")));
+        }
+        Self::open_or_create(path)
+    }
+
+    /// Opens an existing database at `path`, or creates one if it does not exist yet.
+    pub async fn open(path: &str) -> Result<Self, Error> {
+        Self::open_or_create(path)
+    }
+
+    fn open_or_create(path: &str) -> Result<Self, Error> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS kv (key BLOB PRIMARY KEY, value BLOB NOT NULL);
+             CREATE TABLE IF NOT EXISTS checkpoint_layers (layer_id INTEGER PRIMARY KEY);
+             CREATE TABLE IF NOT EXISTS checkpoint_data (
+                 layer_id INTEGER NOT NULL,
+                 key BLOB NOT NULL,
+                 value BLOB NOT NULL
+             );",
+        )?;
+        Ok(SqliteDB { connection })
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Unknown(e.to_string())
+    }
+}
+
+#[async_trait]
+impl KVStorage for SqliteDB {
+    async fn commit_checkpoint(&mut self) -> Result<(), Error> {
+        let tx = self.connection.transaction()?;
+        let next_layer_id: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(layer_id), -1) + 1 FROM checkpoint_layers",
+            [],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO checkpoint_layers (layer_id) VALUES (?1)",
+            params![next_layer_id],
+        )?;
+        tx.execute(
+            "INSERT INTO checkpoint_data (layer_id, key, value)
+             SELECT ?1, key, value FROM kv",
+            params![next_layer_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn revert_to_latest_checkpoint(&mut self) -> Result<(), Error> {
+        let tx = self.connection.transaction()?;
+        let layer_id: Option<i64> =
+            tx.query_row("SELECT MAX(layer_id) FROM checkpoint_layers", [], |row| {
+                row.get(0)
+            })?;
+        let layer_id =
+            layer_id.ok_or_else(|| Error::Unknown("no checkpoint to revert to".to_owned()))?;
+        tx.execute("DELETE FROM kv", [])?;
+        tx.execute(
+            "INSERT INTO kv (key, value)
+             SELECT key, value FROM checkpoint_data WHERE layer_id = ?1",
+            params![layer_id],
+        )?;
+        tx.execute(
+            "DELETE FROM checkpoint_data WHERE layer_id = ?1",
+            params![layer_id],
+        )?;
+        tx.execute(
+            "DELETE FROM checkpoint_layers WHERE layer_id = ?1",
+            params![layer_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn insert_or_update(&mut self, key: Hash256, value: &[u8]) -> Result<(), Error> {
+        self.connection.execute(
+            "INSERT INTO kv (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key.as_ref(), value],
+        )?;
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: Hash256) -> Result<(), Error> {
+        let affected = self
+            .connection
+            .execute("DELETE FROM kv WHERE key = ?1", params![key.as_ref()])?;
+        if affected == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: Hash256) -> Result<Vec<u8>, Error> {
+        self.connection
+            .query_row(
+                "SELECT value FROM kv WHERE key = ?1",
+                params![key.as_ref()],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(Error::NotFound)
+    }
+
+    async fn contain(&self, key: Hash256) -> Result<bool, Error> {
+        let value: Option<i64> = self
+            .connection
+            .query_row(
+                "SELECT 1 FROM kv WHERE key = ?1",
+                params![key.as_ref()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.is_some())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    async fn init(db: &mut SqliteDB) {
+        db.insert_or_update(Hash256::hash("1"), b"1").await.unwrap();
+        db.insert_or_update(Hash256::hash("2"), b"2").await.unwrap();
+        db.insert_or_update(Hash256::hash("3"), b"3").await.unwrap();
+        db.insert_or_update(Hash256::hash("4"), b"4").await.unwrap();
+    }
+
+    fn temp_db_path(name: &str) -> std::path::PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "simperby-kv-storage-test-{name}-{:?}",
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn new_rejects_existing_path() {
+        let path = temp_db_path("new_rejects_existing_path");
+        let path = path.to_str().unwrap();
+        let _db = SqliteDB::new(path).await.unwrap();
+        assert!(SqliteDB::new(path).await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_from_init_db() {
+        let path = temp_db_path("get_from_init_db");
+        let path = path.to_str().unwrap();
+        let mut db = SqliteDB::new(path).await.unwrap();
+        init(&mut db).await;
+        assert_eq!(db.get(Hash256::hash("1")).await.unwrap(), b"1");
+        assert_eq!(db.get(Hash256::hash("4")).await.unwrap(), b"4");
+        assert_eq!(db.get(Hash256::hash("5")).await, Err(Error::NotFound));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn data_survives_a_restart_past_a_checkpoint() {
+        let path = temp_db_path("data_survives_a_restart_past_a_checkpoint");
+        let path = path.to_str().unwrap();
+        {
+            let mut db = SqliteDB::new(path).await.unwrap();
+            init(&mut db).await;
+            db.commit_checkpoint().await.unwrap();
+            db.insert_or_update(Hash256::hash("5"), b"5").await.unwrap();
+        }
+        // Simulate a process restart: drop the connection and reopen it.
+        let db = SqliteDB::open(path).await.unwrap();
+        assert_eq!(db.get(Hash256::hash("1")).await.unwrap(), b"1");
+        assert_eq!(db.get(Hash256::hash("5")).await.unwrap(), b"5");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn revert_without_checkpoint_fails() {
+        let path = temp_db_path("revert_without_checkpoint_fails");
+        let path = path.to_str().unwrap();
+        let mut db = SqliteDB::new(path).await.unwrap();
+        init(&mut db).await;
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn nested_checkpoints_unwind_one_at_a_time() {
+        let path = temp_db_path("nested_checkpoints_unwind_one_at_a_time");
+        let path = path.to_str().unwrap();
+        let mut db = SqliteDB::new(path).await.unwrap();
+        init(&mut db).await;
+        db.commit_checkpoint().await.unwrap(); // checkpoint A: {1, 2, 3, 4}
+        db.insert_or_update(Hash256::hash("5"), b"5").await.unwrap();
+        db.commit_checkpoint().await.unwrap(); // checkpoint B: {1, 2, 3, 4, 5}
+        db.insert_or_update(Hash256::hash("6"), b"6").await.unwrap();
+        assert_eq!(db.get(Hash256::hash("6")).await.unwrap(), b"6");
+
+        db.revert_to_latest_checkpoint().await.unwrap(); // back to checkpoint B
+        assert_eq!(db.get(Hash256::hash("6")).await, Err(Error::NotFound));
+        assert_eq!(db.get(Hash256::hash("5")).await.unwrap(), b"5");
+
+        db.revert_to_latest_checkpoint().await.unwrap(); // back to checkpoint A
+        assert_eq!(db.get(Hash256::hash("5")).await, Err(Error::NotFound));
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn checkpoint_stack_is_consistent_across_the_depth_zero_boundary() {
+        let path = temp_db_path("checkpoint_stack_is_consistent_across_the_depth_zero_boundary");
+        let path = path.to_str().unwrap();
+        let mut db = SqliteDB::new(path).await.unwrap();
+        init(&mut db).await;
+
+        // Round 1: push and revert back to depth 0.
+        db.commit_checkpoint().await.unwrap();
+        db.insert_or_update(Hash256::hash("5"), b"5").await.unwrap();
+        db.revert_to_latest_checkpoint().await.unwrap();
+        assert_eq!(db.get(Hash256::hash("5")).await, Err(Error::NotFound));
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
+
+        // Round 2: climbing back up from depth 0 must start a fresh, independent
+        // checkpoint rather than interacting with the one already unwound above.
+        db.commit_checkpoint().await.unwrap(); // checkpoint C: {1, 2, 3, 4}
+        db.insert_or_update(Hash256::hash("6"), b"6").await.unwrap();
+        assert_eq!(db.get(Hash256::hash("6")).await.unwrap(), b"6");
+        db.revert_to_latest_checkpoint().await.unwrap(); // back to checkpoint C
+        assert_eq!(db.get(Hash256::hash("6")).await, Err(Error::NotFound));
+        assert_eq!(db.get(Hash256::hash("1")).await.unwrap(), b"1");
+        assert!(db.revert_to_latest_checkpoint().await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -10,6 +10,13 @@ pub enum Error {
     /// When the assumption of the method (e.g., there is no merge commit) is violated.
     #[error("the repository is invalid: {0}")]
     InvalidRepository(String),
+    /// When every configured credential (SSH agent, SSH key, username/password) was rejected
+    /// by the remote.
+    #[error("authentication failed for remote '{0}'")]
+    AuthenticationFailed(String),
+    /// When a merge or a stash-pop could not be completed without manual intervention.
+    #[error("merge conflict on: {paths:?}")]
+    MergeConflict { paths: Vec<String> },
     #[error("unknown error: {0}")]
     Unknown(String),
 }
@@ -20,6 +27,21 @@ impl From<git2::Error> for Error {
     }
 }
 
+/// The credentials used to authenticate against a remote that requires them.
+///
+/// All fields are optional; `RawRepository` implementors try each available
+/// method in turn (SSH agent, then explicit SSH key, then username/password)
+/// until the remote accepts one or every option has been exhausted.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    /// The path to a private SSH key, paired with its (optional) passphrase.
+    pub ssh_key_path: Option<String>,
+    pub ssh_key_passphrase: Option<String>,
+    /// A username and password (or personal access token) for HTTPS remotes.
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
 /// A commit without any diff on non-reserved area.
 #[derive(Debug, Clone)]
 pub struct SemanticCommit {
@@ -29,6 +51,322 @@ pub struct SemanticCommit {
     pub reserved_state: Option<ReservedState>,
 }
 
+/// The name and email of a commit's author or committer.
+#[derive(Debug, Clone)]
+pub struct CommitSignature {
+    pub name: String,
+    pub email: String,
+}
+
+/// Structured metadata of a single commit, as an alternative to the raw diff
+/// returned by `show_commit`.
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub hash: CommitHash,
+    pub title: String,
+    pub body: String,
+    pub author: CommitSignature,
+    pub committer: CommitSignature,
+    /// Seconds since the Unix epoch, taken from the committer time.
+    pub timestamp: i64,
+    /// Empty for the initial commit, more than one entry for a merge commit.
+    pub parents: Vec<CommitHash>,
+}
+
+/// Builds the `git2::RemoteCallbacks` credential callback, trying (in order) the
+/// SSH agent, an explicit SSH key, and username/password, based on which of
+/// those `allowed_types` accepts.
+fn credentials_callback(
+    credentials: Credentials,
+) -> impl FnMut(&str, Option<&str>, git2::CredentialType) -> Result<git2::Cred, git2::Error> {
+    let mut tried_agent = false;
+    let mut tried_ssh_key = false;
+    let mut tried_userpass = false;
+    move |_url, username_from_url, allowed_types| {
+        let username = username_from_url.unwrap_or("git");
+        if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+            if !tried_agent {
+                tried_agent = true;
+                if let Ok(cred) = git2::Cred::ssh_key_from_agent(username) {
+                    return Ok(cred);
+                }
+            }
+            if let (false, Some(path)) = (tried_ssh_key, &credentials.ssh_key_path) {
+                tried_ssh_key = true;
+                return git2::Cred::ssh_key(
+                    username,
+                    None,
+                    std::path::Path::new(path),
+                    credentials.ssh_key_passphrase.as_deref(),
+                );
+            }
+        }
+        if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) && !tried_userpass {
+            if let (Some(username), Some(password)) = (&credentials.username, &credentials.password)
+            {
+                tried_userpass = true;
+                return git2::Cred::userpass_plaintext(username, password);
+            }
+        }
+        git2::Cred::default()
+    }
+}
+
+/// Maps a `git2::Error` raised while talking to `remote_name` to
+/// `Error::AuthenticationFailed` if it looks like a rejected credential, or passes
+/// it through otherwise.
+fn map_remote_error(remote_name: &str, error: git2::Error) -> Error {
+    match error.class() {
+        git2::ErrorClass::Ssh | git2::ErrorClass::Http | git2::ErrorClass::Net => {
+            Error::AuthenticationFailed(remote_name.to_owned())
+        }
+        _ => Error::Git2Error(error),
+    }
+}
+
+/// The paths with a conflicting entry in `index`.
+fn conflicted_paths(index: &mut git2::Index) -> Result<Vec<String>, Error> {
+    Ok(index
+        .conflicts()?
+        .filter_map(|conflict| conflict.ok())
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// Merges `from_oid` onto `onto_oid`, returning the resulting commit (which is
+/// `from_oid` itself on a fast-forward).
+fn merge_impl(
+    repo: &git2::Repository,
+    onto_oid: git2::Oid,
+    from_oid: git2::Oid,
+) -> Result<git2::Oid, Error> {
+    let base_oid = repo.merge_base(onto_oid, from_oid)?;
+    if base_oid == onto_oid {
+        return Ok(from_oid);
+    }
+
+    let onto_commit = repo.find_commit(onto_oid)?;
+    let from_commit = repo.find_commit(from_oid)?;
+    let mut index = repo.merge_commits(&onto_commit, &from_commit, None)?;
+    if index.has_conflicts() {
+        return Err(Error::MergeConflict {
+            paths: conflicted_paths(&mut index)?,
+        });
+    }
+
+    let tree_oid = index.write_tree_to(repo)?;
+    let tree = repo.find_tree(tree_oid)?;
+    let signature = repo.signature()?;
+    let merge_commit_oid = repo.commit(
+        None,
+        &signature,
+        &signature,
+        &format!("Merge {from_oid} into {onto_oid}"),
+        &tree,
+        &[&onto_commit, &from_commit],
+    )?;
+    Ok(merge_commit_oid)
+}
+
+/// Rebases the commits reachable from `branch_oid` (and not already reachable from
+/// `onto_oid`) onto `onto_oid`, returning the rebased tip.
+fn rebase_impl(
+    repo: &git2::Repository,
+    branch_oid: git2::Oid,
+    onto_oid: git2::Oid,
+) -> Result<git2::Oid, Error> {
+    let branch_annotated = repo.find_annotated_commit(branch_oid)?;
+    let onto_annotated = repo.find_annotated_commit(onto_oid)?;
+    let mut options = git2::RebaseOptions::new();
+    options.inmemory(true);
+    let mut rebase = repo.rebase(
+        Some(&branch_annotated),
+        None,
+        Some(&onto_annotated),
+        Some(&mut options),
+    )?;
+
+    let mut last_oid = onto_oid;
+    while let Some(operation) = rebase.next() {
+        let operation = operation?;
+        if rebase.inmemory_index()?.has_conflicts() {
+            let paths = conflicted_paths(&mut rebase.inmemory_index()?)?;
+            rebase.abort()?;
+            return Err(Error::MergeConflict { paths });
+        }
+        let original_commit = repo.find_commit(operation.id())?;
+        let author = original_commit.author();
+        let committer = repo.signature()?;
+        last_oid = rebase.commit(Some(&author), &committer, None)?;
+    }
+    rebase.finish(None)?;
+    Ok(last_oid)
+}
+
+fn signature_parts(signature: &git2::Signature) -> (String, String) {
+    (
+        signature.name().unwrap_or_default().to_owned(),
+        signature.email().unwrap_or_default().to_owned(),
+    )
+}
+
+/// Same shape as `CommitInfo`, but keyed on raw `git2::Oid`s so it can be produced
+/// (and tested) without depending on the `CommitHash` newtype.
+struct RawCommitInfo {
+    hash: git2::Oid,
+    title: String,
+    body: String,
+    author: (String, String),
+    committer: (String, String),
+    timestamp: i64,
+    parents: Vec<git2::Oid>,
+}
+
+fn get_commit_log_impl(
+    repo: &git2::Repository,
+    from_oid: git2::Oid,
+    max: Option<usize>,
+) -> Result<Vec<RawCommitInfo>, Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(from_oid)?;
+
+    let mut result = Vec::new();
+    for oid in revwalk {
+        if max.is_some_and(|max| result.len() >= max) {
+            break;
+        }
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        result.push(RawCommitInfo {
+            hash: oid,
+            title: commit.summary().unwrap_or_default().to_owned(),
+            body: commit.body().unwrap_or_default().to_owned(),
+            author: signature_parts(&commit.author()),
+            committer: signature_parts(&commit.committer()),
+            timestamp: commit.time().seconds(),
+            parents: commit.parent_ids().collect(),
+        });
+    }
+    Ok(result)
+}
+
+fn list_submodules_impl(repo: &git2::Repository) -> Result<Vec<(String, String)>, Error> {
+    Ok(repo
+        .submodules()?
+        .iter()
+        .filter_map(|submodule| {
+            let path = submodule.path().to_str()?.to_owned();
+            let url = submodule.url()?.to_owned();
+            Some((path, url))
+        })
+        .collect())
+}
+
+fn update_submodules_impl(
+    repo: &mut git2::Repository,
+    credentials: &Credentials,
+    recursive: bool,
+) -> Result<(), Error> {
+    let mut submodules = repo.submodules()?;
+    for submodule in &mut submodules {
+        submodule.init(false)?;
+
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(credentials_callback(credentials.clone()));
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        let mut update_options = git2::SubmoduleUpdateOptions::new();
+        update_options.fetch(fetch_options);
+
+        let name = submodule.name().unwrap_or_default().to_owned();
+        submodule
+            .update(true, Some(&mut update_options))
+            .map_err(|e| map_remote_error(&name, e))?;
+
+        if recursive {
+            update_submodules_impl(&mut submodule.open()?, credentials, recursive)?;
+        }
+    }
+    Ok(())
+}
+
+fn stash_save_impl(repo: &mut git2::Repository, message: &str) -> Result<(), Error> {
+    let signature = repo.signature()?;
+    repo.stash_save(&signature, message, Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+    Ok(())
+}
+
+fn stash_pop_impl(repo: &mut git2::Repository) -> Result<(), Error> {
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.allow_conflicts(true).conflict_style_merge(true);
+    let mut apply_options = git2::StashApplyOptions::new();
+    apply_options.checkout_options(checkout_builder);
+    repo.stash_apply(0, Some(&mut apply_options))?;
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(Error::MergeConflict {
+            paths: conflicted_paths(&mut index)?,
+        });
+    }
+    repo.stash_drop(0)?;
+    Ok(())
+}
+
+fn stash_list_impl(repo: &git2::Repository) -> Result<Vec<String>, Error> {
+    let mut repo = git2::Repository::open(repo.path())?;
+    let mut messages = Vec::new();
+    repo.stash_foreach(|_index, message, _oid| {
+        messages.push(message.to_owned());
+        true
+    })?;
+    Ok(messages)
+}
+
+fn push_impl(
+    repo: &git2::Repository,
+    credentials: &Credentials,
+    remote_name: &str,
+    refspec: &str,
+    force: bool,
+) -> Result<(), Error> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(credentials_callback(credentials.clone()));
+    let mut push_options = git2::PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+
+    let refspec = if force {
+        format!("+{refspec}")
+    } else {
+        refspec.to_owned()
+    };
+    let mut remote = repo.find_remote(remote_name)?;
+    remote
+        .push(&[refspec.as_str()], Some(&mut push_options))
+        .map_err(|e| map_remote_error(remote_name, e))
+}
+
+fn fetch_all_impl(repo: &git2::Repository, credentials: &Credentials) -> Result<(), Error> {
+    let remote_names: Vec<String> = repo
+        .remotes()?
+        .iter()
+        .filter_map(|name| name.map(str::to_owned))
+        .collect();
+    for remote_name in remote_names {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(credentials_callback(credentials.clone()));
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+
+        let mut remote = repo.find_remote(&remote_name)?;
+        remote
+            .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+            .map_err(|e| map_remote_error(&remote_name, e))?;
+    }
+    Ok(())
+}
+
 /// A raw handle for the local repository.
 ///
 /// It automatically locks the repository once created.
@@ -46,6 +384,26 @@ pub trait RawRepository: Send + Sync + 'static {
     where
         Self: Sized;
 
+    // --------------------------------------------
+    // git2 access, required by the methods below
+    // --------------------------------------------
+
+    /// The underlying git2 repository handle.
+    ///
+    /// Every implementor of this trait is necessarily backed by one to satisfy
+    /// the rest of this trait, so exposing it costs nothing; in return, `push`,
+    /// `fetch_all`, `merge`, `rebase`, `get_commit_log`, and the submodule
+    /// methods below are provided directly in terms of it instead of having to
+    /// be reimplemented by each backend.
+    fn git2_repository(&self) -> &git2::Repository;
+
+    /// Mutable access to the underlying git2 repository handle, needed by the
+    /// git2 APIs that require `&mut Repository`.
+    fn git2_repository_mut(&mut self) -> &mut git2::Repository;
+
+    /// The credentials currently configured via `set_credentials`.
+    fn credentials(&self) -> &Credentials;
+
     // ----------------------
     // Branch-related methods
     // ----------------------
@@ -128,6 +486,26 @@ pub trait RawRepository: Send + Sync + 'static {
     /// Checkouts to the commit and make `HEAD` in a detached mode.
     async fn checkout_detach(&mut self, commit_hash: &CommitHash) -> Result<(), Error>;
 
+    /// Shelves every uncommitted change in the working tree, restoring it to match
+    /// `HEAD`. The change set is pushed onto the stash, tagged with `message`.
+    async fn stash_save(&mut self, message: &str) -> Result<(), Error> {
+        stash_save_impl(self.git2_repository_mut(), message)
+    }
+
+    /// Re-applies and drops the most recently stashed change set onto the working
+    /// tree.
+    ///
+    /// Fails with `Error::MergeConflict` if it cannot be applied cleanly, in which
+    /// case the stash entry is kept rather than silently dropped.
+    async fn stash_pop(&mut self) -> Result<(), Error> {
+        stash_pop_impl(self.git2_repository_mut())
+    }
+
+    /// Lists the messages of every stashed change set, most recent first.
+    async fn stash_list(&self) -> Result<Vec<String>, Error> {
+        stash_list_impl(self.git2_repository())
+    }
+
     // ---------------
     // Various queries
     // ---------------
@@ -143,6 +521,37 @@ pub trait RawRepository: Send + Sync + 'static {
     /// Returns the diff of the given commit.
     async fn show_commit(&self, commit_hash: &CommitHash) -> Result<String, Error>;
 
+    /// Walks the commit graph starting from (and including) `from`, returning the
+    /// structured metadata of each commit in reverse-chronological order.
+    ///
+    /// Unlike `list_ancestors`, this tolerates merge commits, reporting every parent
+    /// of each commit instead of failing.
+    /// * `max`: the maximum number of entries to be returned.
+    async fn get_commit_log(
+        &self,
+        from: &CommitHash,
+        max: Option<usize>,
+    ) -> Result<Vec<CommitInfo>, Error> {
+        Ok(get_commit_log_impl(self.git2_repository(), from.0, max)?
+            .into_iter()
+            .map(|raw| CommitInfo {
+                hash: CommitHash(raw.hash),
+                title: raw.title,
+                body: raw.body,
+                author: CommitSignature {
+                    name: raw.author.0,
+                    email: raw.author.1,
+                },
+                committer: CommitSignature {
+                    name: raw.committer.0,
+                    email: raw.committer.1,
+                },
+                timestamp: raw.timestamp,
+                parents: raw.parents.into_iter().map(CommitHash).collect(),
+            })
+            .collect())
+    }
+
     /// Lists the ancestor commits of the given commit (The first element is the direct parent).
     ///
     /// It fails if there is a merge commit.
@@ -173,6 +582,36 @@ pub trait RawRepository: Send + Sync + 'static {
         commit_hash2: &CommitHash,
     ) -> Result<CommitHash, Error>;
 
+    // --------------------------
+    // Merge/rebase-related methods
+    // --------------------------
+
+    /// Merges `from` onto `onto`, fast-forwarding `onto` if possible and performing
+    /// a three-way merge otherwise. Returns the resulting commit hash (the merge
+    /// commit, or the fast-forwarded commit).
+    ///
+    /// Fails with `Error::MergeConflict` if the merge cannot be completed automatically;
+    /// in that case the working tree and `onto` are left untouched.
+    async fn merge(&mut self, onto: &Branch, from: &CommitHash) -> Result<CommitHash, Error> {
+        let onto_oid = self.locate_branch(onto).await?.0;
+        let result_oid = merge_impl(self.git2_repository(), onto_oid, from.0)?;
+        self.move_branch(onto, &CommitHash(result_oid)).await?;
+        Ok(CommitHash(result_oid))
+    }
+
+    /// Rebases `branch` onto `onto`, replaying each commit of `branch` that is not
+    /// already an ancestor of `onto`, preserving the original author signature.
+    /// Returns the commit hash of the rebased tip.
+    ///
+    /// Fails with `Error::MergeConflict` on the first conflicting step, restoring
+    /// `HEAD` and the working tree to their state before the rebase began.
+    async fn rebase(&mut self, branch: &Branch, onto: &CommitHash) -> Result<CommitHash, Error> {
+        let branch_oid = self.locate_branch(branch).await?.0;
+        let result_oid = rebase_impl(self.git2_repository(), branch_oid, onto.0)?;
+        self.move_branch(branch, &CommitHash(result_oid)).await?;
+        Ok(CommitHash(result_oid))
+    }
+
     // ----------------------
     // Remote-related methods
     // ----------------------
@@ -184,7 +623,32 @@ pub trait RawRepository: Send + Sync + 'static {
     async fn remove_remote(&mut self, remote_name: &str) -> Result<(), Error>;
 
     /// Fetches the remote repository. Same as `git fetch --all -j <LARGE NUMBER>`.
-    async fn fetch_all(&mut self) -> Result<(), Error>;
+    ///
+    /// Authenticates using the credentials set via `set_credentials`, if any.
+    async fn fetch_all(&mut self) -> Result<(), Error> {
+        let credentials = self.credentials().clone();
+        fetch_all_impl(self.git2_repository(), &credentials)
+    }
+
+    /// Pushes `refspec` to `remote_name`. Same as `git push <remote_name> <refspec>`
+    /// (or `git push --force` if `force` is set).
+    ///
+    /// Authenticates using the credentials set via `set_credentials`, if any, and
+    /// fails with `Error::AuthenticationFailed` if the remote rejects all of them.
+    async fn push(&mut self, remote_name: &str, refspec: &str, force: bool) -> Result<(), Error> {
+        let credentials = self.credentials().clone();
+        push_impl(
+            self.git2_repository(),
+            &credentials,
+            remote_name,
+            refspec,
+            force,
+        )
+    }
+
+    /// Sets the credentials used to authenticate `fetch_all` and `push` against
+    /// remotes that require them.
+    fn set_credentials(&mut self, credentials: Credentials);
 
     /// Lists all the remote repositories.
     ///
@@ -197,4 +661,205 @@ pub trait RawRepository: Send + Sync + 'static {
     async fn list_remote_tracking_branches(
         &self,
     ) -> Result<Vec<(String, String, CommitHash)>, Error>;
+
+    // -------------------------
+    // Submodule-related methods
+    // -------------------------
+
+    /// Lists all the submodules declared in `.gitmodules`.
+    ///
+    /// Returns `(path, url)`.
+    async fn list_submodules(&self) -> Result<Vec<(String, String)>, Error> {
+        list_submodules_impl(self.git2_repository())
+    }
+
+    /// Initializes and updates every submodule, materializing their working trees.
+    /// Same as `git submodule update --init` (or `--init --recursive` if `recursive`
+    /// is set).
+    ///
+    /// Uses the same credential layer as `fetch_all`/`push` to authenticate against
+    /// private submodule remotes.
+    async fn update_submodules(&mut self, recursive: bool) -> Result<(), Error> {
+        let credentials = self.credentials().clone();
+        update_submodules_impl(self.git2_repository_mut(), &credentials, recursive)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init_repo(name: &str) -> (std::path::PathBuf, git2::Repository) {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "simperby-raw-test-{name}-{:?}",
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        let mut options = git2::RepositoryInitOptions::new();
+        options.initial_head("refs/heads/master");
+        let repo = git2::Repository::init_opts(&path, &options).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        (path, repo)
+    }
+
+    /// Creates a loose commit with a single file, detached from any ref or HEAD.
+    fn commit(
+        repo: &git2::Repository,
+        filename: &str,
+        contents: &str,
+        parents: &[&git2::Commit],
+        author: Option<&git2::Signature>,
+    ) -> git2::Oid {
+        let blob_oid = repo.blob(contents.as_bytes()).unwrap();
+        let mut tree_builder = repo.treebuilder(None).unwrap();
+        tree_builder.insert(filename, blob_oid, 0o100644).unwrap();
+        let tree_oid = tree_builder.write().unwrap();
+        let tree = repo.find_tree(tree_oid).unwrap();
+        let default_signature = repo.signature().unwrap();
+        let author = author.unwrap_or(&default_signature);
+        repo.commit(None, author, &default_signature, "commit", &tree, parents)
+            .unwrap()
+    }
+
+    /// Writes `filename` into the working tree and commits it onto the current HEAD.
+    fn commit_to_head(repo: &git2::Repository, path: &std::path::Path, filename: &str, contents: &str) {
+        std::fs::write(path.join(filename), contents).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new(filename)).unwrap();
+        index.write().unwrap();
+        let tree_oid = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_oid).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, "commit", &tree, &parents)
+            .unwrap();
+    }
+
+    #[test]
+    fn merge_fast_forward_returns_the_descendant() {
+        let (path, repo) = init_repo("merge_ff");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let descendant = commit(&repo, "b", "1", &[&base_commit], None);
+
+        assert_eq!(merge_impl(&repo, base, descendant).unwrap(), descendant);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn merge_three_way_without_conflict_creates_a_merge_commit() {
+        let (path, repo) = init_repo("merge_three_way");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let onto = commit(&repo, "b", "1", &[&base_commit], None);
+        let from = commit(&repo, "c", "1", &[&base_commit], None);
+
+        let result = merge_impl(&repo, onto, from).unwrap();
+        assert_eq!(repo.find_commit(result).unwrap().parent_count(), 2);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn merge_with_a_conflict_reports_the_conflicting_path() {
+        let (path, repo) = init_repo("merge_conflict");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let onto = commit(&repo, "a", "onto", &[&base_commit], None);
+        let from = commit(&repo, "a", "from", &[&base_commit], None);
+
+        match merge_impl(&repo, onto, from).unwrap_err() {
+            Error::MergeConflict { paths } => assert_eq!(paths, vec!["a".to_owned()]),
+            other => panic!("expected a merge conflict, got {other:?}"),
+        }
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn rebase_replays_commits_preserving_the_original_author() {
+        let (path, repo) = init_repo("rebase");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let onto = commit(&repo, "b", "1", &[&base_commit], None);
+        let original_author =
+            git2::Signature::now("original-author", "author@example.com").unwrap();
+        let branch_tip = commit(
+            &repo,
+            "c",
+            "1",
+            &[&base_commit],
+            Some(&original_author),
+        );
+
+        let result = rebase_impl(&repo, branch_tip, onto).unwrap();
+        let rebased_commit = repo.find_commit(result).unwrap();
+        assert_eq!(rebased_commit.author().name(), Some("original-author"));
+        assert_eq!(rebased_commit.parent_id(0).unwrap(), onto);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn rebase_with_a_conflict_aborts_cleanly() {
+        let (path, repo) = init_repo("rebase_conflict");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let onto = commit(&repo, "a", "onto", &[&base_commit], None);
+        let branch_tip = commit(&repo, "a", "branch", &[&base_commit], None);
+
+        let error = rebase_impl(&repo, branch_tip, onto).unwrap_err();
+        assert!(matches!(error, Error::MergeConflict { .. }));
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn get_commit_log_reports_every_parent_of_a_merge_commit() {
+        let (path, repo) = init_repo("commit_log");
+        let base = commit(&repo, "a", "1", &[], None);
+        let base_commit = repo.find_commit(base).unwrap();
+        let left = commit(&repo, "b", "1", &[&base_commit], None);
+        let right = commit(&repo, "c", "1", &[&base_commit], None);
+        let left_commit = repo.find_commit(left).unwrap();
+        let right_commit = repo.find_commit(right).unwrap();
+        let merge_oid = commit(&repo, "d", "1", &[&left_commit, &right_commit], None);
+
+        let log = get_commit_log_impl(&repo, merge_oid, None).unwrap();
+        assert_eq!(log.len(), 4); // merge, left, right, base
+        assert_eq!(log[0].hash, merge_oid);
+        assert_eq!(log[0].parents, vec![left, right]);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn stash_save_and_pop_restores_the_change() {
+        let (path, mut repo) = init_repo("stash_roundtrip");
+        commit_to_head(&repo, &path, "a", "1");
+        std::fs::write(path.join("a"), "2").unwrap();
+
+        stash_save_impl(&mut repo, "wip").unwrap();
+        assert_eq!(std::fs::read_to_string(path.join("a")).unwrap(), "1");
+
+        stash_pop_impl(&mut repo).unwrap();
+        assert_eq!(std::fs::read_to_string(path.join("a")).unwrap(), "2");
+        assert!(stash_list_impl(&repo).unwrap().is_empty());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn stash_pop_with_a_conflict_keeps_the_stash_entry() {
+        let (path, mut repo) = init_repo("stash_conflict");
+        commit_to_head(&repo, &path, "a", "1");
+        std::fs::write(path.join("a"), "stashed").unwrap();
+        stash_save_impl(&mut repo, "wip").unwrap();
+        commit_to_head(&repo, &path, "a", "conflicting");
+
+        let error = stash_pop_impl(&mut repo).unwrap_err();
+        assert!(matches!(error, Error::MergeConflict { .. }));
+        assert_eq!(stash_list_impl(&repo).unwrap().len(), 1);
+        std::fs::remove_dir_all(path).unwrap();
+    }
 }